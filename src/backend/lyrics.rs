@@ -0,0 +1,124 @@
+use std::error::Error;
+use std::time::Duration;
+use log::{debug, warn};
+use reqwest::blocking::Client;
+use reqwest::header;
+use serde::Deserialize;
+
+use crate::backend::Track;
+
+const USER_AGENT: &str = concat!("melody-magnet/", env!("CARGO_PKG_VERSION"), " ( https://github.com/jwest/melody-magnet )");
+
+#[derive(Debug, Clone)]
+pub struct SyncedLyrics {
+    pub raw: String,
+    pub lines: Vec<(Duration, String)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Lyrics {
+    pub plain: String,
+    pub synced: Option<SyncedLyrics>,
+    pub language: Option<String>,
+    pub copyright: Option<String>,
+}
+
+pub trait LyricsProvider {
+    fn fetch_lyrics(&self, track: &Track) -> Option<Lyrics>;
+}
+
+#[derive(Debug, Deserialize)]
+struct LrcLibResponse {
+    #[serde(rename = "plainLyrics")]
+    plain_lyrics: Option<String>,
+    #[serde(rename = "syncedLyrics")]
+    synced_lyrics: Option<String>,
+}
+
+pub struct LrcLibProvider {
+    client: Client,
+}
+
+impl LrcLibProvider {
+    pub fn init() -> Self {
+        let mut headers = header::HeaderMap::new();
+        headers.insert(header::USER_AGENT, header::HeaderValue::from_static(USER_AGENT));
+
+        let client = Client::builder()
+            .default_headers(headers)
+            .build()
+            .expect("failed to build lyrics client");
+
+        Self { client }
+    }
+
+    fn request(&self, track: &Track) -> Result<LrcLibResponse, Box<dyn Error>> {
+        let album = track.get_album();
+        let url = format!(
+            "https://lrclib.net/api/get?track_name={}&artist_name={}&album_name={}",
+            urlencoding::encode(track.get_title().as_str()),
+            urlencoding::encode(album.get_artist().get_name().as_str()),
+            urlencoding::encode(album.get_title().as_str()),
+        );
+
+        let response = self.client.get(url).send()?;
+        Ok(response.json::<LrcLibResponse>()?)
+    }
+}
+
+impl LyricsProvider for LrcLibProvider {
+    fn fetch_lyrics(&self, track: &Track) -> Option<Lyrics> {
+        let response = match self.request(track) {
+            Ok(response) => response,
+            Err(err) => {
+                warn!("[Lyrics] lookup failed for {:?}: {:?}", track.get_title(), err);
+                return None;
+            },
+        };
+
+        let plain = response.plain_lyrics?;
+        let synced = response.synced_lyrics.map(|raw| SyncedLyrics { lines: parse_lrc(raw.as_str()), raw });
+
+        debug!("[Lyrics] resolved lyrics for {:?} (synced: {})", track.get_title(), synced.is_some());
+
+        Some(Lyrics { plain, synced, language: None, copyright: None })
+    }
+}
+
+/// Parses LRC timestamps (`[mm:ss.xx]`, possibly several per line) into offsets from track start.
+/// Lines that don't start with a timestamp (e.g. `[ar:]`/`[ti:]`/`[al:]` headers) are skipped.
+pub fn parse_lrc(raw: &str) -> Vec<(Duration, String)> {
+    let mut lines = vec![];
+
+    for line in raw.lines() {
+        let mut rest = line;
+        let mut timestamps = vec![];
+
+        while rest.starts_with('[') {
+            let Some(end) = rest.find(']') else { break };
+            let tag = &rest[1..end];
+
+            match parse_timestamp(tag) {
+                Some(duration) => {
+                    timestamps.push(duration);
+                    rest = &rest[end + 1..];
+                },
+                None => break,
+            }
+        }
+
+        for timestamp in &timestamps {
+            lines.push((*timestamp, rest.to_string()));
+        }
+    }
+
+    lines
+}
+
+fn parse_timestamp(tag: &str) -> Option<Duration> {
+    let (minutes, seconds) = tag.split_once(':')?;
+    let minutes: u64 = minutes.parse().ok()?;
+    let seconds: f64 = seconds.parse().ok()?;
+
+    Some(Duration::from_secs(minutes * 60) + Duration::from_secs_f64(seconds))
+}
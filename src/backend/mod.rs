@@ -3,27 +3,70 @@ use std::fs::File;
 use std::io::Write;
 use std::path::PathBuf;
 use bytes::Bytes;
-use chrono::{Datelike, NaiveDate};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::library::MappedForPathName;
 
 pub mod tidal;
+pub mod youtube_music;
+pub mod musicbrainz;
+pub mod lyrics;
 
 pub type AlbumId = String;
 pub type ArtistId = String;
+pub type Mbid = String;
 
 #[derive(Error, Debug)]
 pub enum BackendError {
     #[error("data store disconnected")]
     Disconnect(#[from] io::Error),
-    #[error("request to api failed")]
-    RequestError,
+    #[error("request to api failed: {status:?} {message}")]
+    RequestError { status: Option<u16>, message: String },
+    #[error("resource not found")]
+    NotFound,
 }
 
 pub type BackendResult<T> = Result<T, BackendError>;
 
+fn is_transient_status(status: Option<u16>) -> bool {
+    match status {
+        Some(429) => true,
+        Some(status) => (500..600).contains(&status),
+        None => true,
+    }
+}
+
+/// Retries a request with exponential backoff, but only for transient failures
+/// (429/5xx or no status at all); permanent failures like 404 bail out immediately.
+pub(crate) fn with_backoff<T>(attempts: u32, initial: std::time::Duration, max: std::time::Duration, mut request: impl FnMut() -> BackendResult<T>) -> BackendResult<T> {
+    let mut backoff = initial;
+    let mut last_error = BackendError::RequestError { status: None, message: "exhausted retries".to_string() };
+
+    for attempt in 1..=attempts {
+        match request() {
+            Ok(value) => return Ok(value),
+            Err(err) => {
+                let transient = matches!(&err, BackendError::RequestError { status, .. } if is_transient_status(*status));
+
+                if !transient {
+                    return Err(err);
+                }
+
+                log::info!("request failed (attempt {}/{}): {:?}, retrying in {:?}", attempt, attempts, err, backoff);
+                last_error = err;
+
+                if attempt < attempts {
+                    std::thread::sleep(backoff);
+                    backoff = (backoff * 2).min(max);
+                }
+            },
+        }
+    }
+
+    Err(last_error)
+}
+
 #[derive(Debug)]
 #[derive(Clone)]
 #[derive(Serialize, Deserialize)]
@@ -33,6 +76,16 @@ pub struct Track {
     album: Album,
     track_number: u32,
     volume_number: u32,
+    #[serde(default)]
+    isrc: Option<String>,
+    #[serde(default)]
+    replay_gain: Option<f32>,
+    #[serde(default)]
+    peak: Option<f32>,
+    #[serde(default)]
+    copyright: Option<String>,
+    #[serde(default)]
+    artists: Vec<Artist>,
     // codec ACC
     // Track { id: "83516195", title: "Whatever Lola Wants", album: Album { id: "83516182", artist: Artist { id: "3968881", name: "Bob & Ray" }, title: "Bob And Ray Throw A Stereo Spectacular", release_date: 1900-01-07, number_of_volumes: 1, cover_url: Some("https://resources.tidal.com/images/7b0e1c3d/0718/4669/a7a5/1735f7659610/640x640.jpg"), number_of_tracks: 15 }, track_number: 13, volume_number: 1 }
 }
@@ -50,11 +103,28 @@ impl Track {
     pub fn get_track_number(&self) -> u32 {
         self.track_number
     }
+    pub fn get_isrc(&self) -> Option<String> {
+        self.isrc.clone()
+    }
+    pub fn get_replay_gain(&self) -> Option<f32> {
+        self.replay_gain
+    }
+    pub fn get_peak(&self) -> Option<f32> {
+        self.peak
+    }
+    pub fn get_copyright(&self) -> Option<String> {
+        self.copyright.clone()
+    }
+    pub fn get_artists(&self) -> Vec<Artist> {
+        self.artists.clone()
+    }
 }
 
 impl MappedForPathName for Track {
     fn path_name(&self) -> String {
-        format!("{:02} {} - {}.flac", self.track_number, sanitize_name(self.title.as_str()), sanitize_name(self.album.artist.name.as_str()))
+        // No extension here: the actual container (FLAC/MP4/MP3/...) depends on the
+        // quality the backend served, and is only known once the bytes are downloaded.
+        format!("{:02} {} - {}", self.track_number, sanitize_name(self.title.as_str()), sanitize_name(self.album.artist.name.as_str()))
     }
 }
 
@@ -64,17 +134,91 @@ impl MappedForPathName for Track {
 pub struct Artist {
     id: ArtistId,
     name: String,
+    #[serde(default)]
+    sort: Option<String>,
 }
 
 impl Artist {
     pub fn get_name(&self) -> String {
         self.name.clone()
     }
+    pub fn get_sort_name(&self) -> String {
+        self.sort.clone().unwrap_or_else(|| Self::derive_sort_name(self.name.as_str()))
+    }
+    fn derive_sort_name(name: &str) -> String {
+        match name.strip_prefix("The ") {
+            Some(rest) => format!("{}, The", rest),
+            None => name.to_string(),
+        }
+    }
 }
 
 impl MappedForPathName for Artist {
     fn path_name(&self) -> String {
-        sanitize_name(self.name.as_str())
+        // Intentionally the display name, not `get_sort_name()` - the sort form is for
+        // tagging (ARTISTSORT), and using it here would rename every already-synced
+        // artist's folder on disk and trigger a full re-download.
+        sanitize_name(self.get_name().as_str())
+    }
+}
+
+/// A release date with whatever precision the backend actually provided: Tidal always
+/// gives year-month-day, but other backends may only know the year.
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct AlbumDate {
+    year: i32,
+    month: Option<u32>,
+    day: Option<u32>,
+}
+
+impl AlbumDate {
+    pub fn from_ymd(year: i32, month: Option<u32>, day: Option<u32>) -> Self {
+        Self { year, month, day }
+    }
+
+    pub fn parse(input: &str) -> Option<Self> {
+        let mut parts = input.splitn(3, '-');
+        let year = parts.next()?.parse().ok()?;
+        let month = parts.next().and_then(|month| month.parse().ok());
+        let day = parts.next().and_then(|day| day.parse().ok());
+
+        Some(Self { year, month, day })
+    }
+
+    pub fn year(&self) -> i32 {
+        self.year
+    }
+
+    pub fn month(&self) -> Option<u32> {
+        self.month
+    }
+
+    pub fn day(&self) -> Option<u32> {
+        self.day
+    }
+
+    // Tidal always supplies a full year-month-day date, so emitting "YYYY-MM" whenever a
+    // month is known would change every already-synced Tidal album's folder name from
+    // "YYYY Title" to "YYYY-MM Title" and trigger a full re-download. `month_precision`
+    // opts a library into month-qualified paths (so same-year releases by one artist
+    // stop sorting arbitrarily); existing libraries keep year-only paths by default.
+    fn path_prefix(&self, month_precision: bool) -> String {
+        match self.month {
+            Some(month) if month_precision || self.day.is_none() => format!("{:04}-{:02}", self.year, month),
+            _ => format!("{:04}", self.year),
+        }
+    }
+}
+
+impl std::fmt::Display for AlbumDate {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match (self.month, self.day) {
+            (Some(month), Some(day)) => write!(f, "{:04}-{:02}-{:02}", self.year, month, day),
+            (Some(month), None) => write!(f, "{:04}-{:02}", self.year, month),
+            (None, _) => write!(f, "{:04}", self.year),
+        }
     }
 }
 
@@ -85,11 +229,20 @@ pub struct Album {
     id: AlbumId,
     artist: Artist,
     title: String,
-    // #[serde(with = "ts_seconds_option")]
-    release_date: NaiveDate,
+    release_date: AlbumDate,
     number_of_volumes: u32,
     cover_url: Option<String>,
     number_of_tracks: u32,
+    #[serde(default)]
+    mb_artist_id: Option<Mbid>,
+    #[serde(default)]
+    mb_album_id: Option<Mbid>,
+    #[serde(default = "default_available")]
+    available: bool,
+}
+
+fn default_available() -> bool {
+    true
 }
 
 impl Album {
@@ -111,11 +264,66 @@ impl Album {
     pub fn get_cover_url(&self) -> Option<String> {
         self.cover_url.clone()
     }
+    pub fn get_release_date(&self) -> AlbumDate {
+        self.release_date.clone()
+    }
+    pub fn get_mb_artist_id(&self) -> Option<Mbid> {
+        self.mb_artist_id.clone()
+    }
+    pub fn get_mb_album_id(&self) -> Option<Mbid> {
+        self.mb_album_id.clone()
+    }
+    pub fn set_musicbrainz_ids(&mut self, mb_artist_id: Option<Mbid>, mb_album_id: Option<Mbid>) {
+        self.mb_artist_id = mb_artist_id;
+        self.mb_album_id = mb_album_id;
+    }
+    pub fn is_available(&self) -> bool {
+        self.available
+    }
 }
 
 impl MappedForPathName for Album {
     fn path_name(&self) -> String {
-        format!("{} {}", self.release_date.year(), sanitize_name(self.title.as_str()))
+        self.path_name_with_month_precision(false)
+    }
+}
+
+impl Album {
+    /// Like `path_name`, but lets the caller opt into `YYYY-MM` album folders (see
+    /// `AlbumDate::path_prefix`) instead of always falling back to year-only paths.
+    pub fn path_name_with_month_precision(&self, month_precision: bool) -> String {
+        format!("{} {}", self.release_date.path_prefix(month_precision), sanitize_name(self.title.as_str()))
+    }
+}
+
+/// User-selectable tradeoff between audio quality and file size, configured via
+/// `AUDIO_QUALITY` and honoured by each backend's track-url resolution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Serialize, Deserialize)]
+pub enum QualityPreset {
+    HiResPreferred,
+    LosslessOnly,
+    HighOnly,
+    BestAvailable,
+}
+
+impl Default for QualityPreset {
+    fn default() -> Self {
+        QualityPreset::HiResPreferred
+    }
+}
+
+impl std::str::FromStr for QualityPreset {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "HiResPreferred" => Ok(QualityPreset::HiResPreferred),
+            "LosslessOnly" => Ok(QualityPreset::LosslessOnly),
+            "HighOnly" => Ok(QualityPreset::HighOnly),
+            "BestAvailable" => Ok(QualityPreset::BestAvailable),
+            other => Err(format!("unknown AUDIO_QUALITY preset: {}", other)),
+        }
     }
 }
 
@@ -137,7 +345,7 @@ impl Pagination {
 }
 
 pub trait Backend {
-    fn get_favorite_albums(&self, pagination: Pagination) -> BackendResult<Vec<Album>>;
+    fn get_favorite_albums(&self) -> BackendResult<Vec<Album>>;
 
     fn get_album_tracks(&self, album: &Album) -> BackendResult<Vec<Track>>;
 
@@ -145,13 +353,153 @@ pub trait Backend {
 
     fn download_album_cover(&self, album_id: &Album) -> BackendResult<Bytes>;
 
+    /// Not every backend can search on demand; the default rejects it so backends
+    /// without a search API (e.g. YouTube Music's liked-albums-only Innertube surface)
+    /// don't have to implement a stub.
+    fn search_albums(&self, _query: &str) -> BackendResult<Vec<Album>> {
+        Err(BackendError::RequestError { status: None, message: "search is not supported by this backend".to_string() })
+    }
+    fn search_artists(&self, _query: &str) -> BackendResult<Vec<Artist>> {
+        Err(BackendError::RequestError { status: None, message: "search is not supported by this backend".to_string() })
+    }
+    fn search_tracks(&self, _query: &str) -> BackendResult<Vec<Track>> {
+        Err(BackendError::RequestError { status: None, message: "search is not supported by this backend".to_string() })
+    }
+
     fn serialize(&self) -> String;
     fn deserialize(serialized: String) -> Self where Self: Sized;
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[derive(strum_macros::Display)]
 pub enum BackendType {
     Tidal,
+    YouTubeMusic,
+}
+
+impl std::str::FromStr for BackendType {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "Tidal" => Ok(BackendType::Tidal),
+            "YouTubeMusic" => Ok(BackendType::YouTubeMusic),
+            other => Err(format!("unknown BACKEND: {}", other)),
+        }
+    }
+}
+
+/// Wraps whichever backend `Config`'s `BACKEND` setting selected, so the cron sync
+/// job and the HTTP control API can hold one concretely-typed value regardless of
+/// which streaming service is active, instead of threading a generic/dyn backend
+/// through every call site.
+pub enum ActiveBackend {
+    Tidal(tidal::Tidal),
+    YouTubeMusic(youtube_music::YouTubeMusic),
+}
+
+impl ActiveBackend {
+    pub fn load(session_store: &SessionStore, backend_type: BackendType) -> Self {
+        match backend_type {
+            BackendType::Tidal => ActiveBackend::Tidal(session_store.load::<tidal::Tidal>().unwrap_or_else(tidal::Tidal::init)),
+            BackendType::YouTubeMusic => ActiveBackend::YouTubeMusic(session_store.load::<youtube_music::YouTubeMusic>().unwrap_or_else(youtube_music::YouTubeMusic::init)),
+        }
+    }
+
+    pub fn backend_type(&self) -> BackendType {
+        match self {
+            ActiveBackend::Tidal(_) => BackendType::Tidal,
+            ActiveBackend::YouTubeMusic(_) => BackendType::YouTubeMusic,
+        }
+    }
+
+    pub fn set_quality_preset(&mut self, quality_preset: QualityPreset) {
+        if let ActiveBackend::Tidal(tidal) = self {
+            tidal.set_quality_preset(quality_preset);
+        }
+    }
+
+    pub fn set_metadata_cache_ttl(&mut self, ttl: std::time::Duration) {
+        if let ActiveBackend::Tidal(tidal) = self {
+            tidal.set_metadata_cache_ttl(ttl);
+        }
+    }
+
+    pub fn refresh_token(&mut self) -> BackendResult<()> {
+        match self {
+            ActiveBackend::Tidal(tidal) => tidal.refresh_token(),
+            ActiveBackend::YouTubeMusic(_) => Ok(()),
+        }
+    }
+
+    pub fn save(&self, session_store: &SessionStore) {
+        match self {
+            ActiveBackend::Tidal(tidal) => session_store.save(tidal),
+            ActiveBackend::YouTubeMusic(youtube_music) => session_store.save(youtube_music),
+        }
+    }
+}
+
+impl Backend for ActiveBackend {
+    fn get_favorite_albums(&self) -> BackendResult<Vec<Album>> {
+        match self {
+            ActiveBackend::Tidal(tidal) => tidal.get_favorite_albums(),
+            ActiveBackend::YouTubeMusic(youtube_music) => youtube_music.get_favorite_albums(),
+        }
+    }
+
+    fn get_album_tracks(&self, album: &Album) -> BackendResult<Vec<Track>> {
+        match self {
+            ActiveBackend::Tidal(tidal) => tidal.get_album_tracks(album),
+            ActiveBackend::YouTubeMusic(youtube_music) => youtube_music.get_album_tracks(album),
+        }
+    }
+
+    fn download_track(&self, track: &Track) -> BackendResult<Bytes> {
+        match self {
+            ActiveBackend::Tidal(tidal) => tidal.download_track(track),
+            ActiveBackend::YouTubeMusic(youtube_music) => youtube_music.download_track(track),
+        }
+    }
+
+    fn download_album_cover(&self, album: &Album) -> BackendResult<Bytes> {
+        match self {
+            ActiveBackend::Tidal(tidal) => tidal.download_album_cover(album),
+            ActiveBackend::YouTubeMusic(youtube_music) => youtube_music.download_album_cover(album),
+        }
+    }
+
+    fn search_albums(&self, query: &str) -> BackendResult<Vec<Album>> {
+        match self {
+            ActiveBackend::Tidal(tidal) => tidal.search_albums(query),
+            ActiveBackend::YouTubeMusic(youtube_music) => youtube_music.search_albums(query),
+        }
+    }
+
+    fn search_artists(&self, query: &str) -> BackendResult<Vec<Artist>> {
+        match self {
+            ActiveBackend::Tidal(tidal) => tidal.search_artists(query),
+            ActiveBackend::YouTubeMusic(youtube_music) => youtube_music.search_artists(query),
+        }
+    }
+
+    fn search_tracks(&self, query: &str) -> BackendResult<Vec<Track>> {
+        match self {
+            ActiveBackend::Tidal(tidal) => tidal.search_tracks(query),
+            ActiveBackend::YouTubeMusic(youtube_music) => youtube_music.search_tracks(query),
+        }
+    }
+
+    fn serialize(&self) -> String {
+        match self {
+            ActiveBackend::Tidal(tidal) => tidal.serialize(),
+            ActiveBackend::YouTubeMusic(youtube_music) => youtube_music.serialize(),
+        }
+    }
+
+    fn deserialize(_serialized: String) -> Self {
+        unreachable!("ActiveBackend session round-tripping goes through ActiveBackend::load/save, not Backend::deserialize")
+    }
 }
 
 pub struct SessionStore {
@@ -187,6 +535,7 @@ impl SessionStore {
         let store_path = PathBuf::from(self.path.clone());
         match self.backend_type {
             BackendType::Tidal => store_path.join("tidal_session.json".to_string()).to_owned().to_path_buf(),
+            BackendType::YouTubeMusic => store_path.join("youtube_music_session.json".to_string()).to_owned().to_path_buf(),
         }
     }
 }
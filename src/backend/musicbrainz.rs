@@ -0,0 +1,103 @@
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use log::{debug, warn};
+use reqwest::blocking::Client;
+use reqwest::header;
+use serde_json::Value;
+
+use crate::backend::{Album, Artist, Mbid};
+
+const API_ROOT: &str = "https://musicbrainz.org/ws/2";
+const USER_AGENT: &str = concat!("melody-magnet/", env!("CARGO_PKG_VERSION"), " ( https://github.com/jwest/melody-magnet )");
+const MIN_REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+const DEFAULT_SCORE_THRESHOLD: u8 = 90;
+
+pub trait MetadataResolver {
+    fn resolve_artist(&self, artist: &Artist) -> Option<Mbid>;
+    fn resolve_album(&self, album: &Album) -> Option<Mbid>;
+}
+
+pub struct MusicBrainzResolver {
+    client: Client,
+    score_threshold: u8,
+    last_request: Mutex<Option<Instant>>,
+}
+
+impl MusicBrainzResolver {
+    pub fn init() -> Self {
+        Self::init_with_threshold(DEFAULT_SCORE_THRESHOLD)
+    }
+
+    pub fn init_with_threshold(score_threshold: u8) -> Self {
+        let mut headers = header::HeaderMap::new();
+        headers.insert(header::USER_AGENT, header::HeaderValue::from_static(USER_AGENT));
+
+        let client = Client::builder()
+            .default_headers(headers)
+            .build()
+            .expect("failed to build MusicBrainz client");
+
+        Self { client, score_threshold, last_request: Mutex::new(None) }
+    }
+
+    fn throttle(&self) {
+        let mut last_request = self.last_request.lock().unwrap();
+
+        if let Some(last_request) = *last_request {
+            let elapsed = last_request.elapsed();
+            if elapsed < MIN_REQUEST_INTERVAL {
+                thread::sleep(MIN_REQUEST_INTERVAL - elapsed);
+            }
+        }
+
+        *last_request = Some(Instant::now());
+    }
+
+    fn search(&self, endpoint: &str, query: String) -> Option<Value> {
+        self.throttle();
+
+        let url = format!("{}/{}?query={}&fmt=json", API_ROOT, endpoint, urlencoding::encode(query.as_str()));
+        debug!("[MusicBrainz] search: {}", url);
+
+        match self.client.get(url).send() {
+            Ok(response) if response.status().is_success() => response.json::<Value>().ok(),
+            Ok(response) => {
+                warn!("[MusicBrainz] search failed with status {:?}", response.status());
+                None
+            },
+            Err(err) => {
+                warn!("[MusicBrainz] search request error: {:?}", err);
+                None
+            },
+        }
+    }
+
+    fn best_match(results: &Value, results_key: &str, id_key: &str) -> Option<(Mbid, u8)> {
+        results[results_key].as_array()?.iter()
+            .filter_map(|entry| {
+                let id = entry[id_key].as_str()?.to_string();
+                let score = entry["score"].as_u64().or_else(|| entry["score"].as_str()?.parse().ok())? as u8;
+                Some((id, score))
+            })
+            .max_by_key(|(_, score)| *score)
+    }
+}
+
+impl MetadataResolver for MusicBrainzResolver {
+    fn resolve_artist(&self, artist: &Artist) -> Option<Mbid> {
+        let query = format!("artist:\"{}\"", artist.get_name());
+        let results = self.search("artist", query)?;
+
+        let (mbid, score) = Self::best_match(&results, "artists", "id")?;
+        if score >= self.score_threshold { Some(mbid) } else { None }
+    }
+
+    fn resolve_album(&self, album: &Album) -> Option<Mbid> {
+        let query = format!("artist:\"{}\" AND releasegroup:\"{}\"", album.get_artist().get_name(), album.get_title());
+        let results = self.search("release-group", query)?;
+
+        let (mbid, score) = Self::best_match(&results, "release-groups", "id")?;
+        if score >= self.score_threshold { Some(mbid) } else { None }
+    }
+}
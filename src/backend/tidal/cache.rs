@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use log::trace;
+
+/// A time-based cache: a lookup is a HIT while `now < inserted_at + ttl`, otherwise it's
+/// a MISS and the entry is re-fetched and replaced. Used to avoid hammering the Tidal API
+/// on every cron tick when nothing has actually changed.
+pub(super) struct TtlCache<K, V> {
+    entries: Mutex<HashMap<K, (Instant, V)>>,
+    ttl: Duration,
+}
+
+impl<K, V> TtlCache<K, V> {
+    pub(super) fn init(ttl: Duration) -> Self {
+        Self { entries: Mutex::new(HashMap::new()), ttl }
+    }
+}
+
+impl<K, V> Default for TtlCache<K, V> {
+    fn default() -> Self {
+        Self::init(Duration::from_secs(0))
+    }
+}
+
+impl<K, V> Clone for TtlCache<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            entries: Mutex::new(self.entries.lock().unwrap().clone()),
+            ttl: self.ttl,
+        }
+    }
+}
+
+impl<K, V> std::fmt::Debug for TtlCache<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("TtlCache").field("ttl", &self.ttl).finish()
+    }
+}
+
+impl<K, V> TtlCache<K, V>
+where
+    K: Eq + Hash + Clone + std::fmt::Debug,
+    V: Clone,
+{
+    pub(super) fn get_or_try_insert_with<E>(&self, key: K, fetch: impl FnOnce() -> Result<V, E>) -> Result<V, E> {
+        if let Some(value) = self.get(&key) {
+            trace!("metadata cache HIT for {:?}", key);
+            return Ok(value);
+        }
+
+        trace!("metadata cache MISS for {:?}", key);
+        let value = fetch()?;
+        self.entries.lock().unwrap().insert(key, (Instant::now(), value.clone()));
+        Ok(value)
+    }
+
+    fn get(&self, key: &K) -> Option<V> {
+        let entries = self.entries.lock().unwrap();
+        entries.get(key)
+            .filter(|(inserted_at, _)| inserted_at.elapsed() < self.ttl)
+            .map(|(_, value)| value.clone())
+    }
+}
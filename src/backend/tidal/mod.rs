@@ -1,12 +1,18 @@
+use std::error::Error;
+use std::time::Duration;
 use bytes::Bytes;
-use chrono::NaiveDate;
-use log::{debug, error, info};
+use log::{debug, error};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use crate::backend::{Album, Artist, Backend, BackendError, BackendResult, Pagination, Track};
-use crate::backend::tidal::session::TidalSession;
+use crate::backend::{with_backoff, Album, AlbumDate, Artist, Backend, BackendError, BackendResult, Pagination, QualityPreset, Track};
+use crate::backend::tidal::session::{TidalClientError, TidalSession};
 
 pub mod session;
+mod cache;
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(250);
+const MAX_BACKOFF: Duration = Duration::from_secs(8);
+const MAX_ATTEMPTS: u32 = 5;
 
 const FAVOURITE_ITEMS_PER_PAGE: usize = 100;
 
@@ -27,24 +33,53 @@ impl Tidal {
         self.session.refresh_token().unwrap();
         Ok(())
     }
+
+    pub fn set_quality_preset(&mut self, quality_preset: QualityPreset) {
+        self.session.set_quality_preset(quality_preset);
+    }
+
+    pub fn set_metadata_cache_ttl(&mut self, ttl: Duration) {
+        self.session.set_metadata_cache_ttl(ttl);
+    }
+
+    fn search(&self, query: &str) -> BackendResult<SearchResult> {
+        let country_code = self.session.country_code();
+        let result = self.session.search(query, SEARCH_ITEMS_LIMIT, 0).map_err(to_backend_error)?;
+
+        Ok(SearchResult {
+            artists: parse_items(&result["artists"]["items"], parse_artist),
+            albums: parse_items(&result["albums"]["items"], |item| parse_album(item, country_code.as_str())),
+            tracks: parse_items(&result["tracks"]["items"], |item| parse_track(item, country_code.as_str())),
+        })
+    }
+}
+
+/// Tidal's `/search` endpoint returns all three categories in a single response;
+/// this mirrors that shape instead of making callers dig through raw `Value`.
+pub struct SearchResult {
+    pub artists: Vec<Artist>,
+    pub albums: Vec<Album>,
+    pub tracks: Vec<Track>,
 }
 
 impl Backend for Tidal {
     fn get_favorite_albums(&self) -> BackendResult<Vec<Album>> {
         let mut albums: Vec<Album> = vec![];
         let pagination = Pagination::init(FAVOURITE_ITEMS_PER_PAGE);
+        let country_code = self.session.country_code();
 
         for page in pagination {
             debug!("Tidal::get_favorite_albums: page={:?}", &page);
 
-            let v = self.session.get_favorite_albums(page.limit, page.offset);
+            let v = match self.session.get_favorite_albums(page.limit, page.offset) {
+                Ok(v) => v,
+                Err(err) => {
+                    error!("Backend error: {:?}", err);
+                    return Err(to_backend_error(err));
+                },
+            };
 
-            if v.is_err() {
-                error!("Backend error: {:?}", v.err().unwrap());
-                return Err(BackendError::RequestError);
-            }
-
-            if let Value::Array(items) = &v.unwrap()["items"] {
+            if let Value::Array(items) = &v["items"] {
                 if items.is_empty() {
                     break;
                 }
@@ -116,12 +151,16 @@ impl Backend for Tidal {
                             artist: Artist {
                                 id: item["item"]["artist"]["id"].as_i64().unwrap().to_string(),
                                 name: item["item"]["artist"]["name"].as_str().unwrap().to_string(),
+                                sort: None,
                             },
                             title: item["item"]["title"].as_str().unwrap().to_string(),
-                            release_date: NaiveDate::parse_from_str(item["item"]["releaseDate"].as_str().unwrap(), "%Y-%m-%d").unwrap(),
+                            release_date: AlbumDate::parse(item["item"]["releaseDate"].as_str().unwrap()).unwrap(),
                             number_of_volumes: item["item"]["numberOfVolumes"].as_i64().unwrap() as u32,
                             number_of_tracks: item["item"]["numberOfTracks"].as_i64().unwrap() as u32,
                             cover_url,
+                            mb_artist_id: None,
+                            mb_album_id: None,
+                            available: is_available_in(&item["item"], country_code.as_str()),
                         })
                     }
                 }
@@ -133,6 +172,7 @@ impl Backend for Tidal {
 
     fn get_album_tracks(&self, album: &Album) -> BackendResult<Vec<Track>> {
         let album_details = self.session.get_album(album.id.as_str()).unwrap();
+        let country_code = self.session.country_code();
 
         let mut tracks: Vec<Track> = vec![];
 
@@ -210,12 +250,27 @@ impl Backend for Tidal {
                 //  "volumeNumber": Number(1)
                 // }
 
-                if item["adSupportedStreamReady"].as_bool().is_some_and(|ready| ready) {
+                if item["adSupportedStreamReady"].as_bool().is_some_and(|ready| ready) && is_available_in(item, country_code.as_str()) {
+                    let artists = if let Value::Array(artists) = &item["artists"] {
+                        artists.iter().filter_map(|artist| Some(Artist {
+                            id: artist["id"].as_i64()?.to_string(),
+                            name: artist["name"].as_str()?.to_string(),
+                            sort: None,
+                        })).collect()
+                    } else {
+                        vec![]
+                    };
+
                     let track = Track {
                         id: item["id"].as_i64().unwrap().to_string(),
                         title: item["title"].as_str().unwrap_or_default().to_string(),
                         track_number: item["trackNumber"].as_u64().unwrap() as u32,
                         volume_number: item["volumeNumber"].as_u64().unwrap() as u32,
+                        isrc: item["isrc"].as_str().map(str::to_string),
+                        replay_gain: item["replayGain"].as_f64().map(|gain| gain as f32),
+                        peak: item["peak"].as_f64().map(|peak| peak as f32),
+                        copyright: item["copyright"].as_str().map(str::to_string),
+                        artists,
                         album: album.clone(),
                     };
 
@@ -228,25 +283,27 @@ impl Backend for Tidal {
     }
 
     fn download_track(&self, track: &Track) -> BackendResult<Bytes> {
-        for _ in 1..5 {
-            match self.session.get_track_bytes(track.id.clone()) {
-                Ok(bytes) => return Ok(bytes),
-                Err(err) => info!("error downloading track, retry... ({:?})", err),
-            }
-        }
-
-        Err(BackendError::RequestError)
+        with_backoff(MAX_ATTEMPTS, INITIAL_BACKOFF, MAX_BACKOFF, || {
+            self.session.get_track_bytes(track.id.clone()).map_err(to_backend_error)
+        })
     }
 
     fn download_album_cover(&self, album: &Album) -> BackendResult<Bytes> {
-        for _ in 1..5 {
-            match self.session.get_cover_bytes(album.cover_url.clone().unwrap().clone()) {
-                Ok(bytes) => return Ok(bytes),
-                Err(err) => info!("error downloading track, retry... ({:?})", err),
-            }
-        }
+        with_backoff(MAX_ATTEMPTS, INITIAL_BACKOFF, MAX_BACKOFF, || {
+            self.session.get_cover_bytes(album.cover_url.clone().unwrap().clone()).map_err(to_backend_error)
+        })
+    }
 
-        Err(BackendError::RequestError)
+    fn search_albums(&self, query: &str) -> BackendResult<Vec<Album>> {
+        Ok(self.search(query)?.albums)
+    }
+
+    fn search_artists(&self, query: &str) -> BackendResult<Vec<Artist>> {
+        Ok(self.search(query)?.artists)
+    }
+
+    fn search_tracks(&self, query: &str) -> BackendResult<Vec<Track>> {
+        Ok(self.search(query)?.tracks)
     }
 
     fn serialize(&self) -> String {
@@ -258,6 +315,142 @@ impl Backend for Tidal {
     }
 }
 
+fn to_backend_error(err: Box<dyn Error>) -> BackendError {
+    match err.downcast::<TidalClientError>() {
+        Ok(client_err) => match *client_err {
+            TidalClientError::NotFound => BackendError::NotFound,
+            // No stream exists at the requested quality; treat like a permanent
+            // miss rather than retrying, since a retry would return the same result.
+            TidalClientError::GettingTrackUrlError => BackendError::NotFound,
+            TidalClientError::RequestError { status, message } => BackendError::RequestError { status, message },
+            other => BackendError::RequestError { status: None, message: other.to_string() },
+        },
+        Err(err) => BackendError::RequestError { status: None, message: err.to_string() },
+    }
+}
+
+/// Tidal packs a restriction's allowed/forbidden countries as one string of
+/// concatenated 2-letter codes (no separators), so membership is just a windowed scan.
+fn country_codes_contain(codes: &str, country_code: &str) -> bool {
+    codes.as_bytes().chunks(2).any(|chunk| chunk == country_code.as_bytes())
+}
+
+/// Aggregates every `restrictions` entry on an album/track item into a combined
+/// forbidden/allowed country list, then applies Tidal's availability rule: forbidden
+/// wins if present, otherwise the item is available only if on the allowed list.
+fn is_available_in(item: &Value, country_code: &str) -> bool {
+    let mut forbidden = String::new();
+    let mut allowed = String::new();
+    let mut has_forbidden = false;
+    let mut has_allowed = false;
+
+    if let Value::Array(restrictions) = &item["restrictions"] {
+        for restriction in restrictions {
+            if let Some(codes) = restriction["forbiddenCountries"].as_str() {
+                forbidden.push_str(codes);
+                has_forbidden = true;
+            }
+            if let Some(codes) = restriction["allowedCountries"].as_str() {
+                allowed.push_str(codes);
+                has_allowed = true;
+            }
+        }
+    }
+
+    if !has_forbidden && !has_allowed {
+        return true;
+    }
+
+    (!has_forbidden || !country_codes_contain(&forbidden, country_code))
+        && (!has_allowed || country_codes_contain(&allowed, country_code))
+}
+
+const SEARCH_ITEMS_LIMIT: usize = 25;
+
+fn cover_url_for(cover_id: &str) -> String {
+    let cover_size = CoverSize::CoverSize640 as usize;
+    format!("https://resources.tidal.com/images/{}/{}x{}.jpg", cover_id.replace('-', "/"), cover_size, cover_size)
+}
+
+fn parse_album(item: &Value, country_code: &str) -> Option<Album> {
+    Some(Album {
+        id: item["id"].as_i64()?.to_string(),
+        artist: Artist {
+            id: item["artist"]["id"].as_i64()?.to_string(),
+            name: item["artist"]["name"].as_str()?.to_string(),
+            sort: None,
+        },
+        title: item["title"].as_str()?.to_string(),
+        release_date: AlbumDate::parse(item["releaseDate"].as_str()?)?,
+        number_of_volumes: item["numberOfVolumes"].as_i64()? as u32,
+        number_of_tracks: item["numberOfTracks"].as_i64()? as u32,
+        cover_url: item["cover"].as_str().map(cover_url_for),
+        mb_artist_id: None,
+        mb_album_id: None,
+        available: is_available_in(item, country_code),
+    })
+}
+
+fn parse_artist(item: &Value) -> Option<Artist> {
+    Some(Artist {
+        id: item["id"].as_i64()?.to_string(),
+        name: item["name"].as_str()?.to_string(),
+        sort: None,
+    })
+}
+
+fn parse_track(item: &Value, country_code: &str) -> Option<Track> {
+    let artists = if let Value::Array(artists) = &item["artists"] {
+        artists.iter().filter_map(|artist| Some(Artist {
+            id: artist["id"].as_i64()?.to_string(),
+            name: artist["name"].as_str()?.to_string(),
+            sort: None,
+        })).collect()
+    } else {
+        vec![]
+    };
+
+    // Search results only embed a minimal `album` stub (id/title/cover), unlike the
+    // full album payload `get_album_tracks` has to hand - so build a partial Album
+    // from what's there rather than making a second round-trip just to enqueue it.
+    let album = Album {
+        id: item["album"]["id"].as_i64()?.to_string(),
+        artist: Artist {
+            id: item["artist"]["id"].as_i64()?.to_string(),
+            name: item["artist"]["name"].as_str()?.to_string(),
+            sort: None,
+        },
+        title: item["album"]["title"].as_str()?.to_string(),
+        release_date: AlbumDate::parse(item["streamStartDate"].as_str().unwrap_or("1970-01-01")).unwrap_or(AlbumDate::from_ymd(1970, None, None)),
+        number_of_volumes: item["volumeNumber"].as_i64().unwrap_or(1) as u32,
+        number_of_tracks: 0,
+        cover_url: item["album"]["cover"].as_str().map(cover_url_for),
+        mb_artist_id: None,
+        mb_album_id: None,
+        available: is_available_in(item, country_code),
+    };
+
+    Some(Track {
+        id: item["id"].as_i64()?.to_string(),
+        title: item["title"].as_str().unwrap_or_default().to_string(),
+        track_number: item["trackNumber"].as_u64().unwrap_or(1) as u32,
+        volume_number: item["volumeNumber"].as_u64().unwrap_or(1) as u32,
+        isrc: item["isrc"].as_str().map(str::to_string),
+        replay_gain: item["replayGain"].as_f64().map(|gain| gain as f32),
+        peak: item["peak"].as_f64().map(|peak| peak as f32),
+        copyright: item["copyright"].as_str().map(str::to_string),
+        artists,
+        album,
+    })
+}
+
+fn parse_items<T>(items: &Value, parse: impl Fn(&Value) -> Option<T>) -> Vec<T> {
+    match items {
+        Value::Array(items) => items.iter().filter_map(parse).collect(),
+        _ => vec![],
+    }
+}
+
 enum CoverSize {
     CoverSize80 = 80,
     CoverSize160 = 160,
@@ -10,14 +10,37 @@ use std::thread;
 use log::{error, info};
 use thiserror::Error;
 
+use crate::backend::QualityPreset;
+use crate::backend::tidal::cache::TtlCache;
+
 #[derive(Error, Debug)]
 pub enum TidalClientError {
     #[error("Error on getting track url")]
     GettingTrackUrlError,
     #[error("The token has expired")]
     AuthorizationError,
-    #[error("Request error")]
-    RequestError,
+    #[error("resource not found")]
+    NotFound,
+    #[error("request to api failed: {status:?} {message}")]
+    RequestError { status: Option<u16>, message: String },
+}
+
+/// Reads the status and any `userMessage`/`error.message` field Tidal puts in its
+/// JSON error bodies, so callers can tell a permanent 404 from a transient 5xx/429.
+fn client_error_from_response(response: Response) -> TidalClientError {
+    let status = response.status();
+    let body = response.text().unwrap_or_default();
+
+    if status == StatusCode::NOT_FOUND {
+        return TidalClientError::NotFound;
+    }
+
+    let message = serde_json::from_str::<Value>(&body).ok()
+        .and_then(|json| json["userMessage"].as_str().map(str::to_string)
+            .or_else(|| json["error"]["message"].as_str().map(str::to_string)))
+        .unwrap_or(body);
+
+    TidalClientError::RequestError { status: Some(status.as_u16()), message }
 }
 
 #[derive(Debug)]
@@ -33,6 +56,12 @@ pub struct TidalSession {
     user_id: i64,
     token: String,
     api_path: String,
+    #[serde(default)]
+    quality_preset: QualityPreset,
+    #[serde(skip)]
+    album_cache: TtlCache<String, Value>,
+    #[serde(skip)]
+    favorites_cache: TtlCache<(usize, usize), Value>,
 }
 
 #[derive(Debug)]
@@ -68,6 +97,17 @@ struct RefreshAuthorization {
     access_token: String,
 }
 
+/// Tidal's `audioquality` codes, ordered best-first for each preset. `LosslessOnly`
+/// deliberately excludes `HIGH` so `get_track_url` fails rather than silently downgrading.
+fn quality_candidates(preset: QualityPreset) -> Vec<&'static str> {
+    match preset {
+        QualityPreset::HiResPreferred => vec!["HI_RES_LOSSLESS", "LOSSLESS", "HIGH"],
+        QualityPreset::LosslessOnly => vec!["HI_RES_LOSSLESS", "LOSSLESS"],
+        QualityPreset::HighOnly => vec!["HIGH"],
+        QualityPreset::BestAvailable => vec!["HI_RES_LOSSLESS", "LOSSLESS", "HIGH", "LOW"],
+    }
+}
+
 const CLIENT_ID: &'static str = "zU4XHVVkc2tDPo4t";
 const CLIENT_SECRET: &'static str = "VJKhDFqJPqvsPVNBV6ukXTJmwlvbttP7wlMlrc72se4%3D";
 
@@ -127,6 +167,19 @@ impl TidalSession {
 
         TidalSession::init(session_response).unwrap()
     }
+    pub fn set_quality_preset(&mut self, quality_preset: QualityPreset) {
+        self.quality_preset = quality_preset;
+    }
+
+    pub fn set_metadata_cache_ttl(&mut self, ttl: Duration) {
+        self.album_cache = TtlCache::init(ttl);
+        self.favorites_cache = TtlCache::init(ttl);
+    }
+
+    pub(super) fn country_code(&self) -> String {
+        self.country_code.clone()
+    }
+
     pub fn refresh_token(&mut self) -> Result<(), Box<dyn Error>> {
         let refreshed_session = Self::refresh_access_token(self.refresh_token.clone())?;
 
@@ -172,6 +225,9 @@ impl TidalSession {
                 access_token: config.access_token.clone(),
                 refresh_token: config.refresh_token.clone(),
                 token_type: config.token_type.clone(),
+                quality_preset: QualityPreset::default(),
+                album_cache: TtlCache::default(),
+                favorites_cache: TtlCache::default(),
             });
         }
 
@@ -214,34 +270,74 @@ impl TidalSession {
             .send()?;
         Ok(res)
     }
-    pub(super) fn get_favorite_albums(&self, limit: usize, offset: usize) -> Result<Value, Box<dyn Error>> {
-        let response = self.request(format!("{}/users/{}/favorites/albums?sessionId={}&countryCode={}&limit={}&offset={}", self.api_path, self.user_id, self.session_id, self.country_code, limit, offset))?;
-
-        match response.status() {
-            StatusCode::OK => {
-                let body = response.text()?;
-                let result: Value = serde_json::from_str(&body)?;
-                Ok(result)
-            },
-            StatusCode::UNAUTHORIZED => {
-                error!("Tidal client request error, {:?}", response.text());
-                Err(TidalClientError::AuthorizationError.into())
-            },
-            _ => {
-                error!("Tidal client request error, {:?}, {:?}", response.status(), response.text());
-                Err(TidalClientError::RequestError.into())
-            }
-        }
+    fn request_with_query(&self, url: String, query: &[(&str, String)]) -> Result<Response, Box<dyn Error>> {
+        let res = self.build_client().get(url)
+            .header(header::AUTHORIZATION, header::HeaderValue::from_str(format!("Bearer {}", self.token).as_str()).unwrap())
+            .query(query)
+            .send()?;
+        Ok(res)
     }
-    pub(super) fn get_album(&self, album_id: &str) -> Result<Value, Box<dyn Error>> {
-        let response = self.request(format!("{}/albums/{}/tracks?countryCode={}&deviceType=BROWSER", self.api_path, album_id, self.country_code))?;
+    pub(super) fn search(&self, query: &str, limit: usize, offset: usize) -> Result<Value, Box<dyn Error>> {
+        let response = self.request_with_query(
+            format!("{}/search", self.api_path),
+            &[
+                ("query", query.to_string()),
+                ("countryCode", self.country_code.clone()),
+                ("limit", limit.to_string()),
+                ("offset", offset.to_string()),
+            ],
+        )?;
+
+        if !response.status().is_success() {
+            let err = client_error_from_response(response);
+            error!("Tidal client request error, {:?}", err);
+            return Err(err.into());
+        }
+
         let body = response.text()?;
         let result: Value = serde_json::from_str(&body)?;
         Ok(result)
     }
+    pub(super) fn get_favorite_albums(&self, limit: usize, offset: usize) -> Result<Value, Box<dyn Error>> {
+        self.favorites_cache.get_or_try_insert_with((limit, offset), || {
+            let response = self.request(format!("{}/users/{}/favorites/albums?sessionId={}&countryCode={}&limit={}&offset={}", self.api_path, self.user_id, self.session_id, self.country_code, limit, offset))?;
+
+            match response.status() {
+                StatusCode::OK => {
+                    let body = response.text()?;
+                    let result: Value = serde_json::from_str(&body)?;
+                    Ok(result)
+                },
+                StatusCode::UNAUTHORIZED => {
+                    error!("Tidal client request error, {:?}", response.text());
+                    Err(TidalClientError::AuthorizationError.into())
+                },
+                _ => {
+                    let err = client_error_from_response(response);
+                    error!("Tidal client request error, {:?}", err);
+                    Err(err.into())
+                }
+            }
+        })
+    }
+    pub(super) fn get_album(&self, album_id: &str) -> Result<Value, Box<dyn Error>> {
+        self.album_cache.get_or_try_insert_with(album_id.to_string(), || {
+            let response = self.request(format!("{}/albums/{}/tracks?countryCode={}&deviceType=BROWSER", self.api_path, album_id, self.country_code))?;
+
+            if !response.status().is_success() {
+                let err = client_error_from_response(response);
+                error!("Tidal client request error, {:?}", err);
+                return Err(err.into());
+            }
+
+            let body = response.text()?;
+            let result: Value = serde_json::from_str(&body)?;
+            Ok(result)
+        })
+    }
     fn get_track_url(&self, track_id: String) -> Result<String, Box<dyn Error>> {
         let mut url: Option<String> = None;
-        for quality in vec!["HI_RES_LOSSLESS", "LOSSLESS", "HIGH"] {
+        for quality in quality_candidates(self.quality_preset) {
             let download_url = format!("{}/tracks/{}/urlpostpaywall?sessionId={}&urlusagemode=STREAM&audioquality={}&assetpresentation=FULL", self.api_path, track_id, self.session_id, quality);
             info!("Download track: {}, with url: {}", track_id, download_url);
             let response = self.request(download_url)?;
@@ -0,0 +1,175 @@
+use std::error::Error;
+use std::time::Duration;
+use bytes::Bytes;
+use log::error;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::backend::{with_backoff, Album, AlbumDate, Artist, Backend, BackendError, BackendResult, Track};
+use crate::backend::youtube_music::session::{YouTubeMusicClientError, YouTubeMusicSession};
+
+pub mod session;
+
+const LIKED_ALBUMS_BROWSE_ID: &str = "FEmusic_liked_albums";
+const INITIAL_BACKOFF: Duration = Duration::from_millis(250);
+const MAX_BACKOFF: Duration = Duration::from_secs(8);
+const MAX_ATTEMPTS: u32 = 5;
+
+#[derive(Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct YouTubeMusic {
+    session: YouTubeMusicSession,
+}
+
+impl YouTubeMusic {
+    pub fn init() -> Self {
+        Self {
+            session: YouTubeMusicSession::setup(),
+        }
+    }
+}
+
+impl Backend for YouTubeMusic {
+    fn get_favorite_albums(&self) -> BackendResult<Vec<Album>> {
+        let browse = self.session.browse(LIKED_ALBUMS_BROWSE_ID).map_err(|err| {
+            error!("Backend error: {:?}", err);
+            to_backend_error(err)
+        })?;
+
+        // Liked albums show up as `musicTwoRowItemRenderer` tiles scattered inside the
+        // deeply nested browse response (section -> shelf -> grid -> items), so rather
+        // than walk the exact path we search the whole tree for that renderer key.
+        let albums = find_all(&browse, "musicTwoRowItemRenderer").into_iter()
+            .filter_map(parse_album_tile)
+            .collect();
+
+        Ok(albums)
+    }
+
+    fn get_album_tracks(&self, album: &Album) -> BackendResult<Vec<Track>> {
+        let browse = self.session.browse(album.id.as_str()).map_err(|err| {
+            error!("Backend error: {:?}", err);
+            to_backend_error(err)
+        })?;
+
+        let mut tracks: Vec<Track> = vec![];
+
+        for (index, item) in find_all(&browse, "musicResponsiveListItemRenderer").into_iter().enumerate() {
+            let Some(video_id) = find_all(item, "playlistItemData").first()
+                .and_then(|data| data["videoId"].as_str()) else { continue };
+
+            let title = first_text(item, "flexColumns", 0).unwrap_or_default();
+
+            tracks.push(Track {
+                id: video_id.to_string(),
+                title,
+                track_number: (index + 1) as u32,
+                volume_number: 1,
+                isrc: None,
+                replay_gain: None,
+                peak: None,
+                copyright: None,
+                artists: vec![],
+                album: album.clone(),
+            });
+        }
+
+        Ok(tracks)
+    }
+
+    fn download_track(&self, track: &Track) -> BackendResult<Bytes> {
+        with_backoff(MAX_ATTEMPTS, INITIAL_BACKOFF, MAX_BACKOFF, || {
+            self.session.get_stream_bytes(track.id.as_str()).map_err(to_backend_error)
+        })
+    }
+
+    fn download_album_cover(&self, album: &Album) -> BackendResult<Bytes> {
+        with_backoff(MAX_ATTEMPTS, INITIAL_BACKOFF, MAX_BACKOFF, || {
+            self.session.get_thumbnail_bytes(album.cover_url.clone().unwrap().clone()).map_err(to_backend_error)
+        })
+    }
+
+    fn serialize(&self) -> String {
+        serde_json::to_string(&self).unwrap()
+    }
+
+    fn deserialize(serialized: String) -> Self {
+        serde_json::from_str(&serialized).unwrap()
+    }
+}
+
+fn to_backend_error(err: Box<dyn Error>) -> BackendError {
+    match err.downcast::<YouTubeMusicClientError>() {
+        Ok(client_err) => match *client_err {
+            YouTubeMusicClientError::NotFound => BackendError::NotFound,
+            // No playable stream exists (ciphered/unavailable URL); treat like a permanent
+            // miss rather than retrying, since a retry would return the same result.
+            YouTubeMusicClientError::GettingTrackUrlError => BackendError::NotFound,
+            YouTubeMusicClientError::RequestError { status, message } => BackendError::RequestError { status, message },
+            other => BackendError::RequestError { status: None, message: other.to_string() },
+        },
+        Err(err) => BackendError::RequestError { status: None, message: err.to_string() },
+    }
+}
+
+fn find_all<'a>(value: &'a Value, key: &str) -> Vec<&'a Value> {
+    let mut results = vec![];
+
+    match value {
+        Value::Object(map) => {
+            if let Some(found) = map.get(key) {
+                results.push(found);
+            }
+            for nested in map.values() {
+                results.extend(find_all(nested, key));
+            }
+        },
+        Value::Array(items) => {
+            for item in items {
+                results.extend(find_all(item, key));
+            }
+        },
+        _ => {},
+    }
+
+    results
+}
+
+fn first_text(item: &Value, column_key: &str, index: usize) -> Option<String> {
+    item[column_key].as_array()?
+        .get(index)?["musicResponsiveListItemFlexColumnRenderer"]["text"]["runs"]
+        .as_array()?
+        .first()?["text"].as_str()
+        .map(str::to_string)
+}
+
+fn parse_album_tile(tile: &Value) -> Option<Album> {
+    let browse_endpoint = &tile["navigationEndpoint"]["browseEndpoint"];
+    let album_id = browse_endpoint["browseId"].as_str()?.to_string();
+
+    let title = tile["title"]["runs"].as_array()?.first()?["text"].as_str()?.to_string();
+
+    let artist_run = tile["subtitle"]["runs"].as_array()?.iter()
+        .find(|run| run["navigationEndpoint"]["browseEndpoint"]["browseId"].as_str().is_some_and(|id| id.starts_with("UC")))?;
+    let artist_name = artist_run["text"].as_str()?.to_string();
+    let artist_id = artist_run["navigationEndpoint"]["browseEndpoint"]["browseId"].as_str()?.to_string();
+
+    let cover_url = tile["thumbnailRenderer"]["musicThumbnailRenderer"]["thumbnail"]["thumbnails"]
+        .as_array()
+        .and_then(|thumbnails| thumbnails.last())
+        .and_then(|thumbnail| thumbnail["url"].as_str())
+        .map(str::to_string);
+
+    Some(Album {
+        id: album_id,
+        artist: Artist { id: artist_id, name: artist_name, sort: None },
+        title,
+        // Innertube doesn't surface a release date on the liked-albums tile itself.
+        release_date: AlbumDate::from_ymd(0, None, None),
+        number_of_volumes: 1,
+        number_of_tracks: 0,
+        cover_url,
+        mb_artist_id: None,
+        mb_album_id: None,
+        available: true,
+    })
+}
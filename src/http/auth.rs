@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::fs;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use log::trace;
+use serde::Serialize;
+
+use crate::http::AppState;
+
+/// Long-lived tokens read from `TOKENS_FILE` plus short-lived scoped tokens minted via
+/// `POST /auth/scoped`. Scoped tokens live only in memory and expire on their own, so a
+/// restart (or the background prune sweep) revokes them without touching the master list.
+pub struct TokenStore {
+    master_tokens: Vec<String>,
+    scoped_tokens: Mutex<HashMap<String, Instant>>,
+    scoped_expiry: Duration,
+}
+
+impl TokenStore {
+    pub fn load(tokens_file: &str, scoped_expiry: Duration) -> Self {
+        let master_tokens = fs::read_to_string(tokens_file)
+            .unwrap_or_default()
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        Self {
+            master_tokens,
+            scoped_tokens: Mutex::new(HashMap::new()),
+            scoped_expiry,
+        }
+    }
+
+    pub fn expiry_secs(&self) -> u64 {
+        self.scoped_expiry.as_secs()
+    }
+
+    pub fn is_master(&self, token: &str) -> bool {
+        self.master_tokens.iter().any(|master| master == token)
+    }
+
+    pub fn is_valid(&self, token: &str) -> bool {
+        self.is_master(token) || self.is_scoped(token)
+    }
+
+    fn is_scoped(&self, token: &str) -> bool {
+        self.scoped_tokens.lock().unwrap().get(token)
+            .is_some_and(|issued_at| issued_at.elapsed() < self.scoped_expiry)
+    }
+
+    pub fn mint_scoped(&self) -> String {
+        let token = uuid::Uuid::new_v4().to_string();
+        self.scoped_tokens.lock().unwrap().insert(token.clone(), Instant::now());
+        token
+    }
+
+    /// Drops scoped tokens past their expiry; run periodically from a background task.
+    pub fn prune_expired(&self) {
+        let expiry = self.scoped_expiry;
+        let mut scoped_tokens = self.scoped_tokens.lock().unwrap();
+        let before = scoped_tokens.len();
+        scoped_tokens.retain(|_, issued_at| issued_at.elapsed() < expiry);
+
+        let pruned = before - scoped_tokens.len();
+        if pruned > 0 {
+            trace!("Pruned {} expired scoped token(s)", pruned);
+        }
+    }
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<String> {
+    headers.get(header::AUTHORIZATION)?
+        .to_str().ok()?
+        .strip_prefix("Bearer ")
+        .map(str::to_string)
+}
+
+/// Accepts either a master or a scoped token; used by the data-bearing endpoints.
+pub async fn require_token(State(state): State<Arc<AppState>>, req: Request, next: Next) -> Response {
+    match bearer_token(req.headers()) {
+        Some(token) if state.tokens.is_valid(&token) => next.run(req).await,
+        _ => StatusCode::UNAUTHORIZED.into_response(),
+    }
+}
+
+/// Accepts only a master token; used to gate minting new scoped tokens.
+pub async fn require_master_token(State(state): State<Arc<AppState>>, req: Request, next: Next) -> Response {
+    match bearer_token(req.headers()) {
+        Some(token) if state.tokens.is_master(&token) => next.run(req).await,
+        _ => StatusCode::UNAUTHORIZED.into_response(),
+    }
+}
+
+#[derive(Serialize)]
+struct ScopedTokenResponse {
+    token: String,
+    expires_in: u64,
+}
+
+pub async fn issue_scoped_token(State(state): State<Arc<AppState>>) -> Json<ScopedTokenResponse> {
+    let token = state.tokens.mint_scoped();
+    let expires_in = state.tokens.expiry_secs();
+    Json(ScopedTokenResponse { token, expires_in })
+}
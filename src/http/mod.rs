@@ -0,0 +1,91 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::middleware;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use log::info;
+use serde::Deserialize;
+
+use crate::backend::{ActiveBackend, Album, Backend, SessionStore};
+use crate::http::auth::TokenStore;
+use crate::library::registry::{AlbumRecord, FavouriteAlbums, RegistryStats, SQLiteRegistry};
+use crate::library::Library;
+
+pub mod auth;
+
+/// Shared across the cron scheduler and the HTTP handlers so a manual `POST /albums`
+/// and a scheduled sync tick see the same registry/library/session state.
+pub struct AppState {
+    pub registry: Mutex<SQLiteRegistry>,
+    pub library: Library,
+    pub backend: Mutex<ActiveBackend>,
+    pub session_store: SessionStore,
+    pub tokens: TokenStore,
+}
+
+pub async fn serve(bind: String, port: u16, state: Arc<AppState>) {
+    let data_routes = Router::new()
+        .route("/stats", get(get_stats))
+        .route("/albums", get(list_albums).post(enqueue_album))
+        .route("/search", get(search_albums))
+        .route_layer(middleware::from_fn_with_state(state.clone(), auth::require_token));
+
+    let auth_routes = Router::new()
+        .route("/auth/scoped", post(auth::issue_scoped_token))
+        .route_layer(middleware::from_fn_with_state(state.clone(), auth::require_master_token));
+
+    let app = data_routes.merge(auth_routes).with_state(state.clone());
+
+    tokio::spawn(prune_scoped_tokens(state.clone()));
+
+    let listener = tokio::net::TcpListener::bind(format!("{}:{}", bind, port)).await
+        .expect("failed to bind HTTP control API");
+
+    info!("HTTP control API listening on {}:{}", bind, port);
+
+    axum::serve(listener, app).await.expect("HTTP control API crashed");
+}
+
+async fn prune_scoped_tokens(state: Arc<AppState>) {
+    loop {
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        state.tokens.prune_expired();
+    }
+}
+
+async fn get_stats(State(state): State<Arc<AppState>>) -> Json<RegistryStats> {
+    let stats = state.registry.lock().unwrap().get_stats().expect("problem with aggregate statistics");
+    Json(stats)
+}
+
+async fn list_albums(State(state): State<Arc<AppState>>) -> Json<Vec<AlbumRecord>> {
+    let albums = state.registry.lock().unwrap().list_albums().expect("problem with database");
+    Json(albums)
+}
+
+async fn enqueue_album(State(state): State<Arc<AppState>>, Json(album): Json<Album>) -> StatusCode {
+    let backend_type = state.backend.lock().unwrap().backend_type();
+    match state.registry.lock().unwrap().request_favourite_album(&album, backend_type) {
+        Ok(()) => StatusCode::CREATED,
+        Err(err) => {
+            log::error!("Failed to enqueue album via HTTP API: {:?}", err);
+            StatusCode::CONFLICT
+        },
+    }
+}
+
+#[derive(Deserialize)]
+struct SearchQuery {
+    query: String,
+}
+
+async fn search_albums(State(state): State<Arc<AppState>>, Query(params): Query<SearchQuery>) -> Result<Json<Vec<Album>>, StatusCode> {
+    state.backend.lock().unwrap().search_albums(params.query.as_str())
+        .map(Json)
+        .map_err(|err| {
+            log::error!("Search failed: {:?}", err);
+            StatusCode::BAD_GATEWAY
+        })
+}
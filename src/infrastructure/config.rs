@@ -12,4 +12,20 @@ pub struct Config {
     pub time_zone: String,
     #[env_config(name = "CRON_TAB_DEFINITION", default = "* * * * * *", help = "Cron tab definition")]
     pub cron_tab_definition: String,
+    #[env_config(name = "AUDIO_QUALITY", default = "HiResPreferred", help = "Preferred audio quality: HiResPreferred, LosslessOnly, HighOnly or BestAvailable")]
+    pub audio_quality: String,
+    #[env_config(name = "BACKEND", default = "Tidal", help = "Streaming backend to sync from: Tidal or YouTubeMusic")]
+    pub backend: String,
+    #[env_config(name = "HTTP_PORT", default = "8080", help = "port the HTTP control API listens on")]
+    pub http_port: String,
+    #[env_config(name = "HTTP_BIND", default = "0.0.0.0", help = "bind address for the HTTP control API")]
+    pub http_bind: String,
+    #[env_config(name = "TOKENS_FILE", default = "/config/tokens.txt", help = "file containing one long-lived bearer token per line")]
+    pub tokens_file: String,
+    #[env_config(name = "SCOPED_EXPIRY_DURATION", default = "3600", help = "lifetime in seconds of scoped tokens minted via POST /auth/scoped")]
+    pub scoped_expiry_duration: String,
+    #[env_config(name = "METADATA_CACHE_TTL", default = "300", help = "seconds to cache Tidal album/favourites metadata before re-fetching")]
+    pub metadata_cache_ttl: String,
+    #[env_config(name = "ALBUM_PATH_MONTH_PRECISION", default = "false", help = "include the release month in album folder names (YYYY-MM) instead of just the year - only enable for new libraries, as it renames paths for existing Tidal albums")]
+    pub album_path_month_precision: String,
 }
\ No newline at end of file
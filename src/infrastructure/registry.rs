@@ -2,6 +2,7 @@ use std::error::Error;
 use std::path::PathBuf;
 use chrono::{DateTime, Utc};
 use rusqlite::Connection;
+use serde::Serialize;
 
 use crate::backend::{Album, BackendType};
 use crate::library::MappedForPathName;
@@ -9,27 +10,40 @@ use crate::library::MappedForPathName;
 type RegistryResult<T> = Result<T, Box<dyn Error>>;
 
 pub trait FavouriteAlbums {
-    fn request_favourite_album(&self, album: &Album) -> RegistryResult<()>;
+    fn request_favourite_album(&self, album: &Album, backend_type: BackendType) -> RegistryResult<()>;
+    fn request_unavailable_album(&self, album: &Album, backend_type: BackendType) -> RegistryResult<()>;
     fn is_album_exists(&self, album: &Album) -> RegistryResult<bool>;
     fn mark_album_as_synchronized(&self, album: &Album) -> RegistryResult<()>;
     fn mark_album_as_processing(&self, album: &Album) -> RegistryResult<()>;
     fn get_next_to_synchronize_and_mark_as_processing(&self) -> RegistryResult<Option<Album>>;
+    fn update_musicbrainz_ids(&self, album: &Album) -> RegistryResult<()>;
     fn get_stats(&self) -> RegistryResult<RegistryStats>;
+    fn list_albums(&self) -> RegistryResult<Vec<AlbumRecord>>;
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct RegistryStats {
     album_requested: u64,
     album_processing: u64,
     album_synchronized: u64,
+    album_unavailable: u64,
     count_total: u64,
 }
 
+/// A queued/processing/synchronized/unavailable album as exposed by the HTTP control API.
+#[derive(Debug, Serialize)]
+pub struct AlbumRecord {
+    pub id: String,
+    pub state: String,
+    pub album: Album,
+}
+
 #[derive(strum_macros::Display)]
 enum SynchronizedState {
     Requested,
     Processing,
     Synchronized,
+    Unavailable,
 }
 
 pub struct SQLiteRegistry {
@@ -52,14 +66,16 @@ impl SQLiteRegistry {
     fn setup_database(connection: &Connection) -> RegistryResult<()> {
         connection.execute(
             "CREATE TABLE IF NOT EXISTS album_state (
-            id         INTEGER PRIMARY KEY,
-            state      TEXT NOT NULL,
-            path       TEXT NOT NULL,
-            backend    TEXT NOT NULL,
-            details    BLOB,
-            cover_url  TEXT NOT NULL,
-            created_at TEXT NOT NULL,
-            updated_at TEXT NOT NULL
+            id            INTEGER PRIMARY KEY,
+            state         TEXT NOT NULL,
+            path          TEXT NOT NULL,
+            backend       TEXT NOT NULL,
+            details       BLOB,
+            cover_url     TEXT NOT NULL,
+            mb_artist_id  TEXT,
+            mb_album_id   TEXT,
+            created_at    TEXT NOT NULL,
+            updated_at    TEXT NOT NULL
         )",
             (), // empty list of parameters.
         ).expect("failed to create `album_state` table");
@@ -79,16 +95,37 @@ impl SQLiteRegistry {
 }
 
 impl FavouriteAlbums for SQLiteRegistry {
-    fn request_favourite_album(&self, album: &Album) -> RegistryResult<()> {
+    fn request_favourite_album(&self, album: &Album, backend_type: BackendType) -> RegistryResult<()> {
         self.connection.execute(
-            "INSERT INTO album_state (id, state, path, backend, details, cover_url, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            "INSERT INTO album_state (id, state, path, backend, details, cover_url, mb_artist_id, mb_album_id, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
             (
                 &album.get_id(),
                 SynchronizedState::Requested.to_string(),
                 PathBuf::from(album.get_artist().path_name()).join(album.path_name()).to_str().unwrap(),
-                BackendType::Tidal.to_string(),
+                backend_type.to_string(),
+                serde_json::to_string(&album).unwrap(),
+                album.get_cover_url(),
+                album.get_mb_artist_id(),
+                album.get_mb_album_id(),
+                Utc::now().to_rfc3339(),
+                Utc::now().to_rfc3339()
+            ),
+        )?;
+        Ok(())
+    }
+
+    fn request_unavailable_album(&self, album: &Album, backend_type: BackendType) -> RegistryResult<()> {
+        self.connection.execute(
+            "INSERT INTO album_state (id, state, path, backend, details, cover_url, mb_artist_id, mb_album_id, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            (
+                &album.get_id(),
+                SynchronizedState::Unavailable.to_string(),
+                PathBuf::from(album.get_artist().path_name()).join(album.path_name()).to_str().unwrap(),
+                backend_type.to_string(),
                 serde_json::to_string(&album).unwrap(),
                 album.get_cover_url(),
+                album.get_mb_artist_id(),
+                album.get_mb_album_id(),
                 Utc::now().to_rfc3339(),
                 Utc::now().to_rfc3339()
             ),
@@ -146,18 +183,54 @@ impl FavouriteAlbums for SQLiteRegistry {
         Ok(result)
     }
 
+    fn update_musicbrainz_ids(&self, album: &Album) -> RegistryResult<()> {
+        self.connection.execute(
+            "UPDATE album_state SET details = ?1, mb_artist_id = ?2, mb_album_id = ?3, updated_at = ?4 WHERE id = ?5",
+            (
+                serde_json::to_string(&album).unwrap(),
+                album.get_mb_artist_id(),
+                album.get_mb_album_id(),
+                Utc::now().to_rfc3339(),
+                &album.get_id(),
+            ),
+        )?;
+
+        Ok(())
+    }
+
     fn get_stats(&self) -> RegistryResult<RegistryStats> {
         let album_requested = self.count_by_status(SynchronizedState::Requested)?;
         let album_processing = self.count_by_status(SynchronizedState::Processing)?;
         let album_synchronized = self.count_by_status(SynchronizedState::Synchronized)?;
+        let album_unavailable = self.count_by_status(SynchronizedState::Unavailable)?;
 
         let stats = RegistryStats {
             album_requested,
             album_processing,
             album_synchronized,
-            count_total: album_requested + album_processing + album_synchronized,
+            album_unavailable,
+            count_total: album_requested + album_processing + album_synchronized + album_unavailable,
         };
 
         Ok(stats)
     }
+
+    fn list_albums(&self) -> RegistryResult<Vec<AlbumRecord>> {
+        let mut stmt = self.connection.prepare("SELECT id, state, details FROM album_state")?;
+        let rows = stmt.query_map([], |row| {
+            let id: String = row.get(0)?;
+            let state: String = row.get(1)?;
+            let details: String = row.get(2)?;
+            Ok((id, state, details))
+        })?;
+
+        let mut records = vec![];
+        for row in rows {
+            let (id, state, details) = row?;
+            let album: Album = serde_json::from_str(details.as_str())?;
+            records.push(AlbumRecord { id, state, album });
+        }
+
+        Ok(records)
+    }
 }
\ No newline at end of file
@@ -1,33 +1,45 @@
 use std::error::Error;
 use std::fs;
-use std::path::PathBuf;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
 use bytes::Bytes;
-use metaflac::block::PictureType;
-use metaflac::Tag;
-use crate::backend::{Album, Track};
+use lofty::{Accessor, FileType, ItemKey, Picture, PictureType, Probe, Tag, TagExt, TaggedFileExt};
+use crate::backend::{Album, Artist, Track};
+use crate::backend::lyrics::Lyrics;
 
 pub mod registry;
 
 pub struct Library {
     path: PathBuf,
+    month_precision: bool,
 }
 
 impl Library {
-    pub fn init(path: String) -> Self {
-        Self { path: PathBuf::from(path) }
+    pub fn init(path: String, month_precision: bool) -> Self {
+        Self { path: PathBuf::from(path), month_precision }
     }
     pub fn is_album_exists(&self, album: &Album) -> bool {
-        self.path.clone().join(album.get_artist().path_name()).join(album.path_name()).exists()
+        self.get_album_path(album).exists()
     }
 
-    pub fn save_track(&self, track: &Track, source: &Bytes, cover_source: &Option<Bytes>) -> Result<(), Box<dyn Error>> {
+    pub fn save_track(&self, track: &Track, source: &Bytes, cover_source: &Option<Bytes>, lyrics: &Option<Lyrics>) -> Result<(), Box<dyn Error>> {
         let volume_path = self.get_volume_path(track);
         fs::create_dir_all(&volume_path)?;
 
-        let track_path = self.get_track_path(track);
+        // `Path::with_extension` treats everything after the first `.` in the file name as an
+        // existing extension to replace, which mangles titles containing a `.` (e.g. "Mr.
+        // Brightside"). Build the file name explicitly instead.
+        let track_path = volume_path.join(format!("{}.{}", track.path_name(), extension_for(source)));
         fs::write(&track_path, source)?;
 
-        self.save_track_meta(&track, cover_source)?;
+        self.save_track_meta(&track_path, track, cover_source, lyrics)?;
+
+        if let Some(lyrics) = lyrics {
+            if let Some(synced) = &lyrics.synced {
+                fs::write(volume_path.join(format!("{}.lrc", track.path_name())), &synced.raw)?;
+            }
+        }
+
         Ok(())
     }
 
@@ -43,7 +55,7 @@ impl Library {
     }
 
     fn get_album_path(&self, album: &Album) -> PathBuf {
-        self.path.clone().join(album.get_artist().path_name()).join(album.path_name())
+        self.path.clone().join(album.get_artist().path_name()).join(album.path_name_with_month_precision(self.month_precision))
     }
 
     fn get_volume_path(&self, track: &Track) -> PathBuf {
@@ -56,28 +68,82 @@ impl Library {
         }
     }
 
-    fn get_track_path(&self, track: &Track) -> PathBuf {
-        self.get_volume_path(track).join(track.path_name())
-    }
+    fn save_track_meta(&self, track_path: &Path, track: &Track, cover_source: &Option<Bytes>, lyrics: &Option<Lyrics>) -> Result<(), Box<dyn Error>> {
+        let mut tagged_file = lofty::read_from_path(track_path)?;
+
+        let tag = match tagged_file.primary_tag_mut() {
+            Some(tag) => tag,
+            None => {
+                let tag_type = tagged_file.primary_tag_type();
+                tagged_file.insert_tag(Tag::new(tag_type));
+                tagged_file.primary_tag_mut().expect("tag was just inserted")
+            },
+        };
+
+        tag.set_title(track.get_title());
+        tag.set_album(track.get_album().get_title());
+        tag.insert_text(ItemKey::TrackNumber, track.get_track_number().to_string());
+        tag.insert_text(ItemKey::TrackTotal, track.get_album().get_number_of_tracks().to_string());
+        tag.insert_text(ItemKey::DiscNumber, track.get_volume_number().to_string());
+        tag.insert_text(ItemKey::RecordingDate, track.get_album().get_release_date().to_string());
+
+        let performers = track.get_artists();
+        let artist_name = if performers.is_empty() {
+            track.get_album().get_artist().get_name()
+        } else {
+            performers.iter().map(Artist::get_name).collect::<Vec<_>>().join("; ")
+        };
+        tag.set_artist(artist_name);
+        tag.insert_text(ItemKey::AlbumArtist, track.get_album().get_artist().get_name());
+
+        if let Some(isrc) = track.get_isrc() {
+            tag.insert_text(ItemKey::Isrc, isrc);
+        }
+        if let Some(replay_gain) = track.get_replay_gain() {
+            tag.insert_text(ItemKey::ReplayGainTrackGain, format!("{:.2} dB", replay_gain));
+        }
+        if let Some(peak) = track.get_peak() {
+            tag.insert_text(ItemKey::ReplayGainTrackPeak, format!("{:.6}", peak));
+        }
+        if let Some(copyright) = track.get_copyright() {
+            tag.insert_text(ItemKey::CopyrightMessage, copyright);
+        }
 
-    fn save_track_meta(&self, track: &Track, cover_source: &Option<Bytes>) -> Result<(), Box<dyn Error>> {
-        let mut tag = Tag::read_from_path(self.get_track_path(track))?;
-        let vorbis = tag.vorbis_comments_mut();
-        vorbis.set_track(track.get_track_number());
-        vorbis.set_total_tracks(track.get_album().get_number_of_tracks());
-        vorbis.set_title(vec![track.get_title()]);
-        vorbis.set_album(vec![track.get_album().get_title()]);
-        vorbis.set_artist(vec![track.get_album().get_artist().get_name()]);
+        if let Some(lyrics) = lyrics {
+            tag.insert_text(ItemKey::Lyrics, lyrics.plain.clone());
+        }
 
         if let Some(cover) = cover_source {
-            tag.add_picture("image/png", PictureType::CoverFront, cover.to_vec());
+            let picture = Picture::new_unchecked(PictureType::CoverFront, Some(lofty::MimeType::Png), None, cover.to_vec());
+            tag.push_picture(picture);
         }
 
-        tag.save()?;
+        tagged_file.save_to_path(track_path)?;
         Ok(())
     }
 }
 
+/// Sniffs the downloaded bytes for their actual container so `HighOnly`/lossy quality
+/// presets (which Tidal may serve as AAC/MP4 rather than FLAC) get tagged correctly
+/// and land on disk with a matching extension, instead of being force-fit into `.flac`.
+fn extension_for(source: &Bytes) -> &'static str {
+    let file_type = Probe::new(Cursor::new(source.as_ref()))
+        .guess_file_type()
+        .ok()
+        .and_then(|probe| probe.file_type());
+
+    match file_type {
+        Some(FileType::Flac) => "flac",
+        Some(FileType::Mpeg) => "mp3",
+        Some(FileType::Aac) => "aac",
+        Some(FileType::Mp4) => "m4a",
+        Some(FileType::Vorbis) => "ogg",
+        Some(FileType::Opus) => "opus",
+        Some(FileType::Wav) => "wav",
+        _ => "flac",
+    }
+}
+
 pub trait MappedForPathName {
     fn path_name(&self) -> String;
 }
\ No newline at end of file
@@ -1,10 +1,15 @@
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
 use chrono_tz::Tz;
 use dotenvy::dotenv;
 use env_logger::Target;
 use log::{debug, error, info, warn};
-use crate::backend::{Backend, BackendType, SessionStore};
-use crate::backend::tidal::Tidal;
+use crate::backend::{ActiveBackend, Backend, BackendType, QualityPreset, SessionStore};
+use crate::backend::lyrics::{LrcLibProvider, LyricsProvider};
+use crate::backend::musicbrainz::{MetadataResolver, MusicBrainzResolver};
+use crate::http::auth::TokenStore;
+use crate::http::AppState;
 use crate::infrastructure::config::Config;
 use library::registry::{FavouriteAlbums, SQLiteRegistry};
 use crate::library::Library;
@@ -12,6 +17,7 @@ use crate::library::Library;
 mod backend;
 mod library;
 mod infrastructure;
+mod http;
 
 fn main() {
     dotenv().ok();
@@ -27,12 +33,67 @@ fn main() {
     info!("Local timezone: {}", local_tz);
     info!("Cron tab definition: {}", config.cron_tab_definition);
 
+    let registry = SQLiteRegistry::init(config.database_file_path.clone());
+
+    let month_precision: bool = config.album_path_month_precision.parse().unwrap_or_else(|err| {
+        warn!("Invalid ALBUM_PATH_MONTH_PRECISION, falling back to false: {}", err);
+        false
+    });
+    let library = Library::init(config.library_path.clone(), month_precision);
+
+    let backend_type: BackendType = config.backend.parse().unwrap_or_else(|err| {
+        warn!("Invalid BACKEND, falling back to Tidal: {}", err);
+        BackendType::Tidal
+    });
+
+    let session_store = SessionStore::init(config.session_store_path.clone(), backend_type);
+    let mut backend = ActiveBackend::load(&session_store, backend_type);
+
+    let quality_preset: QualityPreset = config.audio_quality.parse().unwrap_or_else(|err| {
+        warn!("Invalid AUDIO_QUALITY, falling back to HiResPreferred: {}", err);
+        QualityPreset::default()
+    });
+    backend.set_quality_preset(quality_preset);
+
+    let metadata_cache_ttl: u64 = config.metadata_cache_ttl.parse().unwrap_or_else(|err| {
+        warn!("Invalid METADATA_CACHE_TTL, falling back to 300s: {}", err);
+        300
+    });
+    backend.set_metadata_cache_ttl(Duration::from_secs(metadata_cache_ttl));
+
+    let scoped_expiry_secs: u64 = config.scoped_expiry_duration.parse().unwrap_or_else(|err| {
+        warn!("Invalid SCOPED_EXPIRY_DURATION, falling back to 3600s: {}", err);
+        3600
+    });
+    let tokens = TokenStore::load(config.tokens_file.as_str(), Duration::from_secs(scoped_expiry_secs));
+
+    let state = Arc::new(AppState {
+        registry: Mutex::new(registry),
+        library,
+        backend: Mutex::new(backend),
+        session_store,
+        tokens,
+    });
+
+    let http_port: u16 = config.http_port.parse().unwrap_or_else(|err| {
+        warn!("Invalid HTTP_PORT, falling back to 8080: {}", err);
+        8080
+    });
+    let http_bind = config.http_bind.clone();
+    let http_state = state.clone();
+
+    thread::spawn(move || {
+        let runtime = tokio::runtime::Runtime::new().expect("failed to start HTTP control API runtime");
+        runtime.block_on(http::serve(http_bind, http_port, http_state));
+    });
+
     let mut cron = cron_tab::Cron::new(local_tz);
     let lock = Mutex::new(0);
+    let cron_state = state.clone();
 
     cron.add_fn(config.cron_tab_definition.as_str(), move || {
         match lock.try_lock() {
-            Ok(_) => sync_favourites(),
+            Ok(_) => sync_favourites(&cron_state),
             Err(_) => debug!("Next run locked, skipping..."),
         }
     }).unwrap();
@@ -40,26 +101,34 @@ fn main() {
     cron.start_blocking();
 }
 
-fn sync_favourites() {
+fn sync_favourites(state: &Arc<AppState>) {
     info!("Sync favourites cron job started");
 
-    let config = Config::init().expect("Config initialization error!");
-    let registry = SQLiteRegistry::init(config.database_file_path);
-    let library = Library::init(config.library_path);
+    let library = &state.library;
 
-    let session_store = SessionStore::init(config.session_store_path, BackendType::Tidal);
-    let mut tidal_backend = session_store.load::<Tidal>().unwrap_or_else(|| Tidal::init());
+    let metadata_resolver = MusicBrainzResolver::init();
+    let lyrics_provider = LrcLibProvider::init();
 
-    while let Some(album) = registry.get_next_to_synchronize_and_mark_as_processing().expect("problem with database") {
-        print_stats(&registry);
+    // Each registry/backend access below locks its mutex just long enough for that one
+    // operation, rather than for the whole sync run, so the HTTP control API (which
+    // locks the same mutexes) isn't blocked out for the duration of a sync tick.
+    while let Some(mut album) = state.registry.lock().unwrap().get_next_to_synchronize_and_mark_as_processing().expect("problem with database") {
+        print_stats(state);
+
+        if album.get_mb_artist_id().is_none() || album.get_mb_album_id().is_none() {
+            let mb_artist_id = metadata_resolver.resolve_artist(&album.get_artist());
+            let mb_album_id = metadata_resolver.resolve_album(&album);
+            album.set_musicbrainz_ids(mb_artist_id, mb_album_id);
+            state.registry.lock().unwrap().update_musicbrainz_ids(&album).unwrap();
+        }
 
         if !library.is_album_exists(&album) {
-            registry.mark_album_as_processing(&album).unwrap();
+            state.registry.lock().unwrap().mark_album_as_processing(&album).unwrap();
 
-            let tracks = tidal_backend.get_album_tracks(&album).unwrap();
+            let tracks = state.backend.lock().unwrap().get_album_tracks(&album).unwrap();
 
             let cover_source = if album.get_cover_url().is_some() {
-                let cover = tidal_backend.download_album_cover(&album).unwrap();
+                let cover = state.backend.lock().unwrap().download_album_cover(&album).unwrap();
                 library.save_album_cover(&album, &cover).unwrap();
                 Some(cover)
             } else {
@@ -69,39 +138,51 @@ fn sync_favourites() {
             for track in tracks {
                 info!("track: {:?}", track);
 
-                let _ = tidal_backend.download_track(&track).and_then(|track_source| {
-                    if library.save_track(&track, &track_source, &cover_source).is_err() {
+                let lyrics = lyrics_provider.fetch_lyrics(&track);
+
+                let _ = state.backend.lock().unwrap().download_track(&track).and_then(|track_source| {
+                    if library.save_track(&track, &track_source, &cover_source, &lyrics).is_err() {
                         error!("Failed to save track");
                     }
-                    registry.mark_album_as_synchronized(&album).unwrap();
+                    state.registry.lock().unwrap().mark_album_as_synchronized(&album).unwrap();
                     Ok(())
                 });
             }
         }
     }
 
-    print_stats(&registry);
+    print_stats(state);
 
-    match tidal_backend.get_favorite_albums() {
+    let favourite_albums = state.backend.lock().unwrap().get_favorite_albums();
+    match favourite_albums {
         Ok(favourite_albums) => {
+            let backend_type = state.backend.lock().unwrap().backend_type();
+
             for album in favourite_albums {
-                if !&registry.is_album_exists(&album).expect("problem with database") {
-                    let _ = &registry.request_favourite_album(&album).unwrap();
-                    info!("Album requested to synchronize: {:?}", &album);
+                let registry = state.registry.lock().unwrap();
+                if !registry.is_album_exists(&album).expect("problem with database") {
+                    if album.is_available() {
+                        registry.request_favourite_album(&album, backend_type).unwrap();
+                        info!("Album requested to synchronize: {:?}", &album);
+                    } else {
+                        registry.request_unavailable_album(&album, backend_type).unwrap();
+                        info!("Album unavailable in region, skipping: {:?}", &album);
+                    }
                 }
             }
 
-            print_stats(&registry);
+            print_stats(state);
         },
         Err(err) => {
             warn!("Probably token expire, refreshing... ({:?})", err);
-            tidal_backend.refresh_token().unwrap();
-            session_store.save(&tidal_backend);
+            let mut backend = state.backend.lock().unwrap();
+            backend.refresh_token().unwrap();
+            backend.save(&state.session_store);
         }
     }
 }
 
-fn print_stats(registry: &SQLiteRegistry) {
-    let stats = registry.get_stats().expect("problem with aggregate statistics");
+fn print_stats(state: &Arc<AppState>) {
+    let stats = state.registry.lock().unwrap().get_stats().expect("problem with aggregate statistics");
     info!("Current sync stats: {:?}", stats);
 }
\ No newline at end of file